@@ -2,8 +2,9 @@ use crate::{
     config::Style,
     print::{Grapheme, UnicodeWidthGraphemes},
 };
-use nu_ansi_term::{AnsiString, Style as AnsiStyle};
+use nu_ansi_term::{AnsiString, Color as AnsiColor, Style as AnsiStyle};
 use unicode_segmentation::UnicodeSegmentation;
+use vte::{Params, Parser, Perform};
 
 #[derive(Clone, Debug)]
 pub struct SeparatorSegment {
@@ -53,6 +54,22 @@ impl SeparatorSegment {
     pub fn set_style(&mut self, style: Option<AnsiStyle>) {
         self.style = style.map(|s| s.into());
     }
+
+    /// Returns a copy of this segment truncated to the leading graphemes that
+    /// fit within `max_width` display columns, preserving its style. Returns
+    /// `None` if no whole grapheme fits.
+    fn clip_to_width(&self, max_width: usize) -> Option<Self> {
+        let clipped = clip_str_to_width(&self.value, max_width);
+        if clipped.is_empty() {
+            None
+        } else {
+            Some(Self {
+                style: self.style,
+                value: String::from(clipped),
+                left: self.left,
+            })
+        }
+    }
 }
 /// Type that holds text with an associated style
 #[derive(Clone, Debug)]
@@ -62,14 +79,44 @@ pub struct TextSegment {
 
     /// The string value of the current segment.
     value: String,
+
+    /// An optional OSC 8 target URI. When set and hyperlink emission is
+    /// enabled, `ansi_string` wraps the painted text in an OSC 8 hyperlink;
+    /// `value()`/`width_graphemes()` are unaffected, so this carries zero
+    /// width impact either way.
+    uri: Option<String>,
 }
 
 impl TextSegment {
     // Returns the AnsiString of the segment value
-    fn ansi_string(&self, prev: Option<&AnsiStyle>) -> AnsiString {
-        match self.style {
+    fn ansi_string(&self, prev: Option<&AnsiStyle>, hyperlinks_enabled: bool) -> AnsiString {
+        let painted = match self.style {
             Some(style) => style.to_ansi_style(prev).paint(&self.value),
             None => AnsiString::from(&self.value),
+        };
+
+        match &self.uri {
+            Some(uri) if hyperlinks_enabled => {
+                let uri = sanitize_hyperlink_uri(uri);
+                AnsiString::from(format!("\x1b]8;;{uri}\x1b\\{painted}\x1b]8;;\x1b\\"))
+            }
+            _ => painted,
+        }
+    }
+
+    /// Returns a copy of this segment truncated to the leading graphemes that
+    /// fit within `max_width` display columns, preserving its style. Returns
+    /// `None` if no whole grapheme fits.
+    fn clip_to_width(&self, max_width: usize) -> Option<Self> {
+        let clipped = clip_str_to_width(&self.value, max_width);
+        if clipped.is_empty() {
+            None
+        } else {
+            Some(Self {
+                style: self.style,
+                value: String::from(clipped),
+                uri: self.uri.clone(),
+            })
         }
     }
 }
@@ -94,7 +141,11 @@ impl FillSegment {
                 .cycle()
                 .scan(0usize, |len, g| {
                     *len += Grapheme(g).width();
-                    if *len <= w { Some(g) } else { None }
+                    if *len <= w {
+                        Some(g)
+                    } else {
+                        None
+                    }
                 })
                 .collect::<String>(),
             None => String::from(&self.value),
@@ -104,6 +155,21 @@ impl FillSegment {
             None => AnsiString::from(s),
         }
     }
+
+    /// Returns a copy of this segment truncated to the leading graphemes that
+    /// fit within `max_width` display columns, preserving its style. Returns
+    /// `None` if no whole grapheme fits.
+    fn clip_to_width(&self, max_width: usize) -> Option<Self> {
+        let clipped = clip_str_to_width(&self.value, max_width);
+        if clipped.is_empty() {
+            None
+        } else {
+            Some(Self {
+                style: self.style,
+                value: String::from(clipped),
+            })
+        }
+    }
 }
 
 #[cfg(test)]
@@ -135,6 +201,182 @@ mod fill_seg_tests {
     }
 }
 
+#[cfg(test)]
+mod clip_line_tests {
+    use super::Segment;
+    use nu_ansi_term::Color;
+
+    #[test]
+    fn clip_line_keeps_segments_that_already_fit() {
+        let style = Some(Color::Blue.bold().into());
+        let segments = Segment::from_text(style, "hello");
+        let clipped = Segment::clip_line(&segments, 10);
+        assert_eq!(
+            clipped.iter().map(Segment::width_graphemes).sum::<usize>(),
+            5
+        );
+    }
+
+    #[test]
+    fn clip_line_splits_at_grapheme_boundary_and_keeps_style() {
+        let style = Some(Color::Blue.bold().into());
+        let segments = Segment::from_text(style, "hello world");
+        let clipped = Segment::clip_line(&segments, 5);
+        assert_eq!(
+            clipped.iter().map(Segment::width_graphemes).sum::<usize>(),
+            5
+        );
+        assert_eq!(clipped[0].value(), "hello");
+        assert_eq!(clipped[0].style(), style.map(|s| s.to_ansi_style(None)));
+    }
+
+    #[test]
+    fn clip_line_never_splits_a_wide_grapheme_in_half() {
+        let segments = Segment::from_text(None, "a🟦b");
+        // "a🟦" is 3 columns wide; width 2 must drop the emoji entirely
+        // rather than emit half of it.
+        let clipped = Segment::clip_line(&segments, 2);
+        assert_eq!(clipped[0].value(), "a");
+    }
+
+    #[test]
+    fn clip_line_resets_width_on_line_term() {
+        let segments = Segment::from_text(None, "hello\nworld");
+        let clipped = Segment::clip_line(&segments, 5);
+        let values: Vec<&str> = clipped.iter().map(Segment::value).collect();
+        assert_eq!(values, vec!["hello", "\n", "world"]);
+    }
+
+    #[test]
+    fn clip_line_clips_each_line_independently_when_an_earlier_line_overflows() {
+        let segments = Segment::from_text(None, "toolong\nok");
+        let clipped = Segment::clip_line(&segments, 4);
+        let values: Vec<&str> = clipped.iter().map(Segment::value).collect();
+        assert_eq!(values, vec!["tool", "\n", "ok"]);
+    }
+
+    #[test]
+    fn clip_line_drops_segments_after_an_unresolved_fill() {
+        // The fill's unexpanded pattern is only 1 column wide, but it's meant
+        // to expand and consume the rest of the line at print time, so
+        // nothing after it should survive clipping.
+        let mut segments = Segment::from_text(None, "ab");
+        segments.push(Segment::fill(None, "."));
+        segments.extend(Segment::from_text(None, "cd"));
+        let clipped = Segment::clip_line(&segments, 3);
+        let values: Vec<&str> = clipped.iter().map(Segment::value).collect();
+        assert_eq!(values, vec!["ab", "."]);
+    }
+}
+
+#[cfg(test)]
+mod from_ansi_text_tests {
+    use super::Segment;
+    use nu_ansi_term::{Color, Style as AnsiStyle};
+
+    #[test]
+    fn plain_text_is_a_single_segment() {
+        let segments = Segment::from_ansi_text(None, "hello");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].value(), "hello");
+    }
+
+    #[test]
+    fn sgr_codes_split_runs_and_apply_style() {
+        let segments = Segment::from_ansi_text(None, "\x1b[31mred\x1b[0mplain");
+        let values: Vec<&str> = segments.iter().map(Segment::value).collect();
+        assert_eq!(values, vec!["red", "plain"]);
+        assert_eq!(segments[0].style(), Some(Color::Red.normal()));
+        assert_eq!(segments[1].style(), Some(AnsiStyle::default()));
+    }
+
+    #[test]
+    fn unrecognized_escapes_are_dropped_from_visible_text() {
+        let segments = Segment::from_ansi_text(None, "\x1b]0;ignored title\x07visible");
+        let values: Vec<&str> = segments.iter().map(Segment::value).collect();
+        assert_eq!(values, vec!["visible"]);
+    }
+
+    #[test]
+    fn truncated_extended_color_does_not_leak_into_later_sgr_codes() {
+        let segments = Segment::from_ansi_text(None, "\x1b[38;2;1mtext");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].value(), "text");
+        assert_eq!(segments[0].style(), Some(AnsiStyle::default()));
+    }
+}
+
+#[cfg(test)]
+mod wrap_line_tests {
+    use super::Segment;
+
+    fn values(segments: &[Segment]) -> Vec<&str> {
+        segments.iter().map(Segment::value).collect()
+    }
+
+    #[test]
+    fn short_line_is_unaffected() {
+        let segments = Segment::from_text(None, "hello world");
+        let wrapped = Segment::wrap_line(&segments, 80);
+        assert_eq!(values(&wrapped), vec!["hello", " ", "world"]);
+    }
+
+    #[test]
+    fn wraps_at_word_boundary_and_drops_break_whitespace() {
+        let segments = Segment::from_text(None, "hello world");
+        let wrapped = Segment::wrap_line(&segments, 5);
+        assert_eq!(values(&wrapped), vec!["hello", "\n", "world"]);
+    }
+
+    #[test]
+    fn word_wider_than_line_is_emitted_alone_without_looping() {
+        let segments = Segment::from_text(None, "superlongword hi");
+        let wrapped = Segment::wrap_line(&segments, 5);
+        assert_eq!(values(&wrapped), vec!["superlongword", "\n", "hi"]);
+    }
+
+    #[test]
+    fn separator_segments_are_non_breakable_atoms() {
+        let segments = vec![
+            Segment::separator(None, ">"),
+            Segment::Text(super::TextSegment {
+                style: None,
+                value: String::from(" text"),
+                uri: None,
+            }),
+        ];
+        let wrapped = Segment::wrap_line(&segments, 2);
+        assert_eq!(values(&wrapped)[0], ">");
+    }
+}
+
+#[cfg(test)]
+mod hyperlink_tests {
+    use super::Segment;
+
+    #[test]
+    fn hyperlink_wraps_text_only_when_enabled() {
+        let segment = Segment::hyperlink(None, "starship", "https://starship.rs");
+        assert_eq!(segment.value(), "starship");
+        assert_eq!(segment.width_graphemes(), 8);
+
+        assert_eq!(segment.ansi_string(None, false).to_string(), "starship");
+        assert_eq!(
+            segment.ansi_string(None, true).to_string(),
+            "\x1b]8;;https://starship.rs\x1b\\starship\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn hyperlink_uri_control_chars_are_percent_encoded() {
+        let segment = Segment::hyperlink(None, "evil", "https://x/\x1b]0;pwned\x07");
+        assert_eq!(
+            segment.ansi_string(None, true).to_string(),
+            "\x1b]8;;https://x/%1B]0;pwned%07\x1b\\evil\x1b]8;;\x1b\\"
+        );
+    }
+}
+
 /// A segment is a styled text chunk ready for printing.
 #[derive(Clone, Debug)]
 pub enum Segment {
@@ -158,11 +400,43 @@ impl Segment {
             segs.push(Self::Text(TextSegment {
                 value: String::from(s),
                 style,
+                uri: None,
             }))
         });
         segs
     }
 
+    /// Creates new segments from text that may itself contain SGR escape
+    /// sequences (e.g. the output of a `custom`/`cmd` command), so the
+    /// embedded styling participates in width math instead of corrupting it.
+    /// `default_style` is restored on a bare reset code (`\x1b[0m`).
+    pub fn from_ansi_text<T>(default_style: Option<Style>, value: T) -> Vec<Self>
+    where
+        T: Into<String>,
+    {
+        let mut performer = AnsiPerformer::new(default_style);
+        let mut parser = Parser::new();
+        parser.advance(&mut performer, value.into().as_bytes());
+        performer.finish()
+    }
+
+    /// Creates a new text segment carrying an OSC 8 hyperlink to `uri`. The
+    /// visible text and its width are unaffected; whether the link actually
+    /// gets emitted depends on the `hyperlinks_enabled` flag passed to
+    /// [`Segment::ansi_string`] — when disabled, this renders identically to
+    /// a plain styled text segment.
+    pub fn hyperlink<T, U>(style: Option<Style>, value: T, uri: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        Self::Text(TextSegment {
+            style,
+            value: value.into(),
+            uri: Some(uri.into()),
+        })
+    }
+
     /// Creates a new fill segment
     pub fn fill<T>(style: Option<Style>, value: T) -> Self
     where
@@ -228,11 +502,14 @@ impl Segment {
         }
     }
 
-    // Returns the AnsiString of the segment value, not including its prefix and suffix
-    pub fn ansi_string(&self, prev: Option<&AnsiStyle>) -> AnsiString {
+    // Returns the AnsiString of the segment value, not including its prefix and suffix.
+    // `hyperlinks_enabled` gates OSC 8 emission for segments carrying a `uri` (see
+    // `Segment::hyperlink`); callers should set it based on the user's config and the
+    // terminal's OSC 8 support.
+    pub fn ansi_string(&self, prev: Option<&AnsiStyle>, hyperlinks_enabled: bool) -> AnsiString {
         match self {
             Self::Fill(fs) => fs.ansi_string(None, prev),
-            Self::Text(ts) => ts.ansi_string(prev),
+            Self::Text(ts) => ts.ansi_string(prev, hyperlinks_enabled),
             Self::Separator(ss) => ss.ansi_string(prev),
             Self::LineTerm => AnsiString::from(LINE_TERMINATOR_STRING),
         }
@@ -250,6 +527,345 @@ impl Segment {
     pub fn is_linebreak(&self) -> bool {
         matches!(self, Self::LineTerm)
     }
+
+    /// Clips a rendered line to `max_width` display columns, splitting the
+    /// segment straddling the boundary at the last grapheme that still fits
+    /// rather than cutting mid-grapheme. Styles of retained text are kept
+    /// intact; a `LineTerm` resets the running width so multi-line input is
+    /// clipped line by line.
+    ///
+    /// A `Fill` segment's rendered width isn't known here: it's a repeat
+    /// pattern that only gets a concrete width when printed, via
+    /// `FillSegment::ansi_string`'s `width` argument. Since a `Fill` is
+    /// designed to expand and consume whatever space is left on its line,
+    /// it's budgeted here as taking up the rest of `max_width` rather than
+    /// its unexpanded pattern width, so later segments on the same line are
+    /// correctly dropped instead of silently overflowing `max_width`.
+    pub fn clip_line(segments: &[Self], max_width: usize) -> Vec<Self> {
+        let mut result = Vec::new();
+        let mut width = 0usize;
+        // Once the current line has hit max_width, further segments on that
+        // line are dropped, but later lines (after the next LineTerm) must
+        // still be clipped independently.
+        let mut skip_rest_of_line = false;
+
+        for segment in segments {
+            if segment.is_linebreak() {
+                result.push(Self::LineTerm);
+                width = 0;
+                skip_rest_of_line = false;
+                continue;
+            }
+
+            if skip_rest_of_line {
+                continue;
+            }
+
+            if width >= max_width {
+                skip_rest_of_line = true;
+                continue;
+            }
+
+            if matches!(segment, Self::Fill(_)) {
+                result.push(segment.clone());
+                skip_rest_of_line = true;
+                continue;
+            }
+
+            let seg_width = segment.width_graphemes();
+            if width + seg_width <= max_width {
+                width += seg_width;
+                result.push(segment.clone());
+                continue;
+            }
+
+            if let Some(clipped) = segment.clip_to_width(max_width - width) {
+                result.push(clipped);
+            }
+            skip_rest_of_line = true;
+        }
+
+        result
+    }
+
+    /// Returns a copy of this segment truncated to the leading graphemes that
+    /// fit within `max_width` display columns.
+    fn clip_to_width(&self, max_width: usize) -> Option<Self> {
+        match self {
+            Self::Text(ts) => ts.clip_to_width(max_width).map(Self::Text),
+            Self::Fill(fs) => fs.clip_to_width(max_width).map(Self::Fill),
+            Self::Separator(ss) => ss.clip_to_width(max_width).map(Self::Separator),
+            Self::LineTerm => None,
+        }
+    }
+
+    /// Soft-wraps a line to `max_width` display columns by inserting
+    /// `LineTerm`s at word boundaries, carrying each piece's original style
+    /// across the break. Separator and fill segments are treated as
+    /// non-breakable atoms. A single word wider than `max_width` is emitted
+    /// on its own line rather than looping forever trying to fit it.
+    pub fn wrap_line(segments: &[Self], max_width: usize) -> Vec<Self> {
+        let mut result = Vec::new();
+        let mut width = 0usize;
+        let mut pending_spaces: Vec<Self> = Vec::new();
+
+        for token in Self::tokenize_for_wrap(segments) {
+            match token {
+                WrapToken::Break => {
+                    pending_spaces.clear();
+                    result.push(Self::LineTerm);
+                    width = 0;
+                }
+                WrapToken::Space(space) => {
+                    pending_spaces.push(space);
+                }
+                WrapToken::Word(word) => {
+                    let word_width = word.width_graphemes();
+                    let space_width: usize =
+                        pending_spaces.iter().map(Self::width_graphemes).sum();
+
+                    if width > 0 && width + space_width + word_width > max_width {
+                        result.push(Self::LineTerm);
+                        width = 0;
+                        pending_spaces.clear();
+                    } else {
+                        width += space_width;
+                        result.append(&mut pending_spaces);
+                    }
+
+                    width += word_width;
+                    result.push(word);
+                }
+            }
+        }
+
+        result.append(&mut pending_spaces);
+        result
+    }
+
+    /// Splits segments into word-wrap tokens: `TextSegment`s are broken at
+    /// Unicode word boundaries into non-breakable words and the (breakable)
+    /// whitespace between them, while separator/fill segments and line
+    /// breaks pass through as single atoms.
+    fn tokenize_for_wrap(segments: &[Self]) -> Vec<WrapToken> {
+        let mut tokens = Vec::new();
+        for segment in segments {
+            match segment {
+                Self::Text(ts) => {
+                    for word in ts.value.split_word_bounds() {
+                        let piece = Self::Text(TextSegment {
+                            style: ts.style,
+                            value: String::from(word),
+                            uri: ts.uri.clone(),
+                        });
+                        if word.chars().all(char::is_whitespace) {
+                            tokens.push(WrapToken::Space(piece));
+                        } else {
+                            tokens.push(WrapToken::Word(piece));
+                        }
+                    }
+                }
+                Self::LineTerm => tokens.push(WrapToken::Break),
+                other => tokens.push(WrapToken::Word(other.clone())),
+            }
+        }
+        tokens
+    }
+}
+
+/// A single unit produced by [`Segment::tokenize_for_wrap`].
+enum WrapToken {
+    /// A non-breakable chunk of text, or an opaque separator/fill segment.
+    Word(Segment),
+    /// Whitespace between two words; dropped if a break happens here.
+    Space(Segment),
+    /// An explicit line break already present in the input.
+    Break,
+}
+
+/// Folds the SGR escape sequences of an ANSI-styled string into a sequence
+/// of `Segment::Text`s, one per run of text sharing a style: `print`/UTF-8
+/// runs accumulate text, a CSI `m` dispatch updates the running style, a
+/// newline becomes a `Segment::LineTerm`, and anything else (other control
+/// bytes, other CSI finals, OSC, etc.) is silently dropped from the visible
+/// output.
+struct AnsiPerformer {
+    style: AnsiStyle,
+    default_style: AnsiStyle,
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl AnsiPerformer {
+    fn new(default_style: Option<Style>) -> Self {
+        let style = default_style
+            .map(|s| s.to_ansi_style(None))
+            .unwrap_or_default();
+        Self {
+            style,
+            default_style: style,
+            text: String::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.segments.push(Segment::Text(TextSegment {
+                style: Some(self.style.into()),
+                value: std::mem::take(&mut self.text),
+                uri: None,
+            }));
+        }
+    }
+
+    fn finish(mut self) -> Vec<Segment> {
+        self.flush();
+        self.segments
+    }
+}
+
+impl Perform for AnsiPerformer {
+    fn print(&mut self, c: char) {
+        self.text.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        if byte == b'\n' {
+            self.flush();
+            self.segments.push(Segment::LineTerm);
+        }
+    }
+
+    fn csi_dispatch(
+        &mut self,
+        params: &Params,
+        _intermediates: &[u8],
+        _ignore: bool,
+        action: char,
+    ) {
+        if action != 'm' {
+            return;
+        }
+        self.flush();
+
+        let codes: Vec<u16> = params
+            .iter()
+            .map(|p| p.first().copied().unwrap_or(0))
+            .collect();
+        if codes.is_empty() {
+            self.style = self.default_style;
+            return;
+        }
+
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => self.style = self.default_style,
+                1 => self.style.is_bold = true,
+                3 => self.style.is_italic = true,
+                4 => self.style.is_underline = true,
+                n @ 30..=37 => self.style.foreground = Some(sgr_color(n - 30)),
+                n @ 90..=97 => self.style.foreground = Some(sgr_color(n - 90 + 8)),
+                38 => {
+                    let (color, consumed) = extended_sgr_color(&codes[i + 1..]);
+                    if let Some(color) = color {
+                        self.style.foreground = Some(color);
+                    }
+                    i += consumed;
+                }
+                n @ 40..=47 => self.style.background = Some(sgr_color(n - 40)),
+                n @ 100..=107 => self.style.background = Some(sgr_color(n - 100 + 8)),
+                48 => {
+                    let (color, consumed) = extended_sgr_color(&codes[i + 1..]);
+                    if let Some(color) = color {
+                        self.style.background = Some(color);
+                    }
+                    i += consumed;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Maps a 0-15 SGR color index (after subtracting its base offset) to the
+/// matching `nu_ansi_term` color, covering both normal (30-37/40-47) and
+/// bright (90-97/100-107) ranges.
+fn sgr_color(index: u16) -> AnsiColor {
+    match index {
+        0 => AnsiColor::Black,
+        1 => AnsiColor::Red,
+        2 => AnsiColor::Green,
+        3 => AnsiColor::Yellow,
+        4 => AnsiColor::Blue,
+        5 => AnsiColor::Purple,
+        6 => AnsiColor::Cyan,
+        7 => AnsiColor::White,
+        8 => AnsiColor::DarkGray,
+        9 => AnsiColor::LightRed,
+        10 => AnsiColor::LightGreen,
+        11 => AnsiColor::LightYellow,
+        12 => AnsiColor::LightBlue,
+        13 => AnsiColor::LightPurple,
+        14 => AnsiColor::LightCyan,
+        15 => AnsiColor::LightGray,
+        _ => AnsiColor::Fixed(index as u8),
+    }
+}
+
+/// Parses the `5;n` (256-color) or `2;r;g;b` (truecolor) tail that follows a
+/// `38`/`48` SGR code, returning the color (if the tail was well-formed) and
+/// how many further codes to advance past. A truncated or unrecognized tail
+/// still reports how much of `rest` belongs to it, so the caller can skip
+/// those codes instead of reprocessing them as unrelated standalone SGR
+/// codes.
+fn extended_sgr_color(rest: &[u16]) -> (Option<AnsiColor>, usize) {
+    match rest.first() {
+        Some(5) => match rest.get(1) {
+            Some(&n) => (Some(AnsiColor::Fixed(n as u8)), 2),
+            None => (None, rest.len()),
+        },
+        Some(2) if rest.len() >= 4 => (
+            Some(AnsiColor::Rgb(rest[1] as u8, rest[2] as u8, rest[3] as u8)),
+            4,
+        ),
+        _ => (None, rest.len()),
+    }
+}
+
+/// Percent-encodes ASCII control characters (ESC, BEL, etc.) in a hyperlink
+/// URI so a `uri` built from untrusted input (a branch name, a remote URL)
+/// can't break out of the OSC 8 wrapper and inject arbitrary escape
+/// sequences into the terminal.
+fn sanitize_hyperlink_uri(uri: &str) -> String {
+    let mut sanitized = String::with_capacity(uri.len());
+    for c in uri.chars() {
+        if c.is_ascii_control() {
+            sanitized.push_str(&format!("%{:02X}", c as u8));
+        } else {
+            sanitized.push(c);
+        }
+    }
+    sanitized
+}
+
+/// Returns the longest prefix of `value` whose display width (in grapheme
+/// cells, not bytes or chars) is `<= max_width`, so a multi-cell grapheme is
+/// never split in half.
+fn clip_str_to_width(value: &str, max_width: usize) -> &str {
+    let mut width = 0usize;
+    let mut end = 0usize;
+    for g in value.graphemes(true) {
+        let grapheme_width = Grapheme(g).width();
+        if width + grapheme_width > max_width {
+            break;
+        }
+        width += grapheme_width;
+        end += g.len();
+    }
+    &value[..end]
 }
 
 const LINE_TERMINATOR: char = '\n';